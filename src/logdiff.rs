@@ -0,0 +1,33 @@
+//! Diffing the start vs. end toolchain logs so that classification only
+//! sees lines that are actually new, instead of misattributing pre-existing
+//! flaky tests or warnings-as-errors as regressions introduced by the end
+//! toolchain.
+
+use std::collections::HashSet;
+
+lazy_static::lazy_static! {
+    static ref ANSI_ESCAPE: regex::Regex = regex::Regex::new(r"\x1b\[[0-9;]*[a-zA-Z]").unwrap();
+    static ref TIMESTAMP: regex::Regex =
+        regex::Regex::new(r"\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}(\.\d+)?Z?").unwrap();
+    static ref ABS_PATH: regex::Regex = regex::Regex::new(r"(?:/[^\s`']+)+").unwrap();
+}
+
+/// Normalizes a log line so that incidental differences between two runs
+/// (ANSI colors, timestamps, absolute paths into per-run temp dirs) don't
+/// make an unchanged line look new.
+fn normalize(line: &str) -> String {
+    let line = ANSI_ESCAPE.replace_all(line, "");
+    let line = TIMESTAMP.replace_all(&line, "<timestamp>");
+    let line = ABS_PATH.replace_all(&line, "<path>");
+    line.into_owned()
+}
+
+/// Returns the lines of `end_log` (in their original, un-normalized form)
+/// that have no normalized match anywhere in `start_log`.
+pub fn new_lines<'a>(start_log: &str, end_log: &'a str) -> Vec<&'a str> {
+    let start_lines: HashSet<String> = start_log.lines().map(normalize).collect();
+    end_log
+        .lines()
+        .filter(|line| !start_lines.contains(&normalize(line)))
+        .collect()
+}
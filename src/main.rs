@@ -1,73 +1,32 @@
 use flate2::read::GzDecoder;
-use regex::Regex;
 use std::collections::BTreeMap;
 use std::fmt::{self, Write as _};
 use std::io::BufReader;
 use std::io::{Read, Write as _};
 
-lazy_static::lazy_static! {
-    static ref CLIENT: reqwest::Client = reqwest::Client::new();
-}
+mod logdiff;
+mod matchers;
+mod owners;
+mod report;
+
+use matchers::{MatcherEngine, SuspectedCause};
+use owners::owners_for_crate_name;
+use report::Row;
 
 percent_encoding::define_encode_set! {
     pub REPORT_ENCODE_SET = [percent_encoding::DEFAULT_ENCODE_SET] | { '+' }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize)]
+#[serde(tag = "type")]
 enum CrateId {
+    #[serde(rename = "registry")]
     CratesIo { package: String, version: String },
+    #[serde(rename = "github")]
     GitHub { user: String, repository: String },
 }
 
-#[derive(serde::Deserialize, Debug)]
-struct CratesIoOwners {
-    users: Vec<CratesIoUser>,
-}
-
-#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
-#[serde(rename_all = "lowercase")]
-enum CratesIoUserKind {
-    User,
-    Team,
-}
-
-#[derive(serde::Deserialize, Debug)]
-struct CratesIoUser {
-    kind: CratesIoUserKind,
-    login: String,
-    url: String,
-}
-
-impl CratesIoUser {
-    fn gh_username(&self) -> Option<&str> {
-        let prefix = "https://github.com/";
-        if self.url.starts_with(prefix) && self.kind == CratesIoUserKind::User {
-            Some(&self.login)
-        } else {
-            None
-        }
-    }
-}
-
-fn owners_for_crate_name(package: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
-    let owners: CratesIoOwners = CLIENT
-        .get(&format!(
-            "https://crates.io/api/v1/crates/{}/owners",
-            package
-        ))
-        .header(reqwest::header::USER_AGENT, "crater-generate-report")
-        .send()
-        .unwrap()
-        .json()?;
-
-    Ok(owners
-        .users
-        .into_iter()
-        .flat_map(|u| u.gh_username().map(String::from))
-        .collect())
-}
-
-fn format_owners_to_cc(owners: &[String]) -> String {
+pub fn format_owners_to_cc(owners: &[String]) -> String {
     owners
         .into_iter()
         .map(|o| format!("@{}", o))
@@ -133,21 +92,21 @@ impl Config {
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
-enum CcWho {
+pub enum CcWho {
     All,
     Roots,
     None,
 }
 
 impl CcWho {
-    fn causes(self) -> bool {
+    pub fn causes(self) -> bool {
         match self {
             CcWho::All => true,
             CcWho::Roots | CcWho::None => false,
         }
     }
 
-    fn roots(self) -> bool {
+    pub fn roots(self) -> bool {
         match self {
             CcWho::All | CcWho::Roots => true,
             CcWho::None => false,
@@ -155,54 +114,93 @@ impl CcWho {
     }
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = std::env::args().collect::<Vec<_>>();
-    let experiment = args.get(1).unwrap_or_else(|| {
-        eprintln!("Usage: {} <experiment name>", args[0]);
-        std::process::exit(1);
-    });
-    let cc_ty = args.get(2).unwrap_or_else(|| {
-        eprintln!(
-            "Usage: {} <experiment name> <all|roots|none|print-list>",
-            args[0]
-        );
-        std::process::exit(1);
-    });
-    let cc_ty = match cc_ty.as_str() {
-        "all" => CcWho::All,
-        "roots" => CcWho::Roots,
-        "none" => CcWho::None,
-        "print-list" => CcWho::None,
-        _ => {
-            eprintln!("Wrong second parameter: {:?}", cc_ty);
-            eprintln!("Usage: {} <experiment name> <all|roots|none>", args[0]);
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Markdown,
+    Json,
+}
+
+/// Pulls `--format <value>` out of `args` wherever it appears, leaving the
+/// remaining positional arguments untouched.
+fn take_format_flag(args: &mut Vec<String>) -> OutputFormat {
+    let format = match args.iter().position(|a| a == "--format") {
+        Some(idx) if idx + 1 < args.len() => {
+            args.remove(idx);
+            args.remove(idx)
+        }
+        Some(_) => {
+            eprintln!("--format requires a value (markdown or json)");
             std::process::exit(1);
         }
+        None => return OutputFormat::Markdown,
     };
-
-    let url = format!(
-        "https://crater-reports.s3.amazonaws.com/{}/config.json",
-        experiment
-    );
-    let config: Config = reqwest::get(&url)
-        .unwrap_or_else(|e| {
-            eprintln!("failed to get {:?}: {:?}", url, e);
+    match format.as_str() {
+        "markdown" => OutputFormat::Markdown,
+        "json" => OutputFormat::Json,
+        _ => {
+            eprintln!("Unknown --format {:?}, expected markdown or json", format);
             std::process::exit(1);
-        })
-        .json()
-        .unwrap_or_else(|e| {
-            eprintln!("failed to deserialize response from {:?}: {:?}", url, e);
+        }
+    }
+}
+
+/// The result categories a crater experiment buckets crates into; see
+/// `logs-archives/*.tar.gz` in an experiment's report.
+const ALL_CATEGORIES: &[&str] = &["regressed", "fixed", "broken", "spurious-regressed"];
+
+/// Pulls `--categories <value>[,<value>...]` out of `args` wherever it
+/// appears, leaving the remaining positional arguments untouched. `all`
+/// expands to every category in [`ALL_CATEGORIES`]. Defaults to `regressed`
+/// alone, matching this tool's original, regressions-only behavior.
+fn take_categories_flag(args: &mut Vec<String>) -> Vec<String> {
+    let value = match args.iter().position(|a| a == "--categories") {
+        Some(idx) if idx + 1 < args.len() => {
+            args.remove(idx);
+            args.remove(idx)
+        }
+        Some(_) => {
+            eprintln!("--categories requires a value, e.g. regressed,fixed or all");
             std::process::exit(1);
-        });
+        }
+        None => return vec!["regressed".to_string()],
+    };
+    if value == "all" {
+        return ALL_CATEGORIES.iter().map(|s| s.to_string()).collect();
+    }
+    value.split(',').map(|s| s.to_string()).collect()
+}
 
+type CategoryRows = BTreeMap<SuspectedCause, Vec<Row>>;
+
+/// Downloads and classifies one category's log archive (e.g. `regressed`,
+/// `fixed`) for `experiment`, writing `<category>-crate-list.txt` as a side
+/// effect, the same way the original regressions-only tool did. Returns
+/// `Ok(None)` if the experiment simply has no archive for that category
+/// (e.g. no crate was `fixed`), so a `--categories all` run still reports
+/// the rest instead of aborting.
+fn process_category(
+    experiment: &str,
+    category: &str,
+    config: &Config,
+    matcher_engine: &MatcherEngine,
+) -> Result<Option<CategoryRows>, Box<dyn std::error::Error>> {
     let url = format!(
-        "https://crater-reports.s3.amazonaws.com/{}/logs-archives/regressed.tar.gz",
-        experiment
+        "https://crater-reports.s3.amazonaws.com/{}/logs-archives/{}.tar.gz",
+        experiment, category
     );
     let res = reqwest::get(&url).unwrap_or_else(|e| {
-        eprintln!("failed to download regressed logs from {:?}: {:?}", url, e);
+        eprintln!("failed to download {} logs from {:?}: {:?}", category, url, e);
         std::process::exit(1);
     });
+    if !res.status().is_success() {
+        eprintln!(
+            "skipping category {:?}: {:?} returned {}",
+            category,
+            url,
+            res.status()
+        );
+        return Ok(None);
+    }
     let mut tarball = tar::Archive::new(GzDecoder::new(BufReader::new(res)));
     let mut regressions = BTreeMap::new();
     for entry in tarball.entries()? {
@@ -255,11 +253,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         regressions
             .entry(res.clone())
             .or_insert_with(|| Regression::new(res))
-            .insert(&config, &toolchain, log);
+            .insert(config, &toolchain, log);
     }
 
-    let compile_regex = Regex::new(r#"[Cc]ould not compile `([^)]+?)`"#).unwrap();
-    let document_regex = Regex::new(r#"Could not document `([^`)]+?)`"#).unwrap();
     let mut rows = BTreeMap::new();
     let mut crate_list = String::new();
     for regression in regressions.values() {
@@ -271,124 +267,102 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             };
             writeln!(&mut crate_list, "{}", id).unwrap();
         }
-        let mut crates = Vec::new();
-        for capture in compile_regex.captures_iter(&end_log) {
-            crates.push(SuspectedCause::CompileError {
-                crate_name: capture[1].into(),
-            });
-        }
-        for capture in document_regex.captures_iter(&end_log) {
-            crates.push(SuspectedCause::DocumentaionError {
-                crate_name: capture[1].into(),
-            });
-        }
         let name = match &regression.id {
             CrateId::CratesIo { package, .. } => package.clone(),
             CrateId::GitHub { user, repository } => format!("{}/{}", user, repository),
         };
-        if end_log.contains("error: test failed, to rerun pass '--lib'") {
-            crates.push(SuspectedCause::TestFailure {
-                crate_name: name.clone(),
-            });
-        }
-        if end_log.contains("error: test failed, to rerun pass '--doc'") {
-            crates.push(SuspectedCause::DocTestFailure {
-                crate_name: name.clone(),
-            });
-        }
+        let new_errors = logdiff::new_lines(regression.log(ToolchainType::Start), end_log);
+        let new_log = new_errors.join("\n");
+        let mut crates = matcher_engine.classify(&new_log, &name);
+        let cc = regression.id.owners().unwrap_or_else(|e| {
+            eprintln!("no owner? failed to look up owners for {}: {}", regression.id, e);
+            Vec::new()
+        });
+        let row = Row {
+            id: regression.id.clone(),
+            start_log_url: regression.log_url(config, ToolchainType::Start),
+            end_log_url: regression.log_url(config, ToolchainType::End),
+            owners: cc,
+            new_errors: new_errors.into_iter().map(String::from).collect(),
+        };
         if crates.len() == 1 {
             let cause = crates.pop().unwrap();
-
-            rows.entry(cause).or_insert_with(Vec::new).push((
-                &regression.id,
-                "start",
-                regression.log_url(&config, ToolchainType::Start),
-                "end",
-                regression.log_url(&config, ToolchainType::End),
-                format_owners_to_cc(&regression.id.owners().expect(&format!("{}", regression.id))),
-            ));
+            rows.entry(cause).or_insert_with(Vec::new).push(row);
         } else {
-            rows.entry(SuspectedCause::Unknown)
+            rows.entry(SuspectedCause::unknown())
                 .or_insert_with(Vec::new)
-                .push((
-                    &regression.id,
-                    "start",
-                    regression.log_url(&config, ToolchainType::Start),
-                    "end",
-                    regression.log_url(&config, ToolchainType::End),
-                    format_owners_to_cc(
-                        &regression.id.owners().expect(&format!("{}", regression.id)),
-                    ),
-                ));
+                .push(row);
         }
     }
-    std::fs::write("crate-list.txt", crate_list.trim_end_matches(",")).unwrap();
-
-    let mut table = String::new();
-    for (cause, affected) in rows {
-        if affected.len() == 1 {
-            let row = &affected[0];
-            writeln!(
-                table,
-                " * root: {}: [{}]({}) v. [{}]({}){}",
-                row.0,
-                row.1,
-                row.2,
-                row.3,
-                row.4,
-                if cc_ty.roots() {
-                    format!("; cc {}", row.5)
-                } else {
-                    String::new()
-                }
-            )
-            .unwrap();
-        } else {
-            writeln!(
-                table,
-                "\nroot: {} - {} detected crates which regressed due to this{}",
-                cause,
-                affected.len(),
-                if cc_ty.roots() {
-                    match cause
-                        .crate_name()
-                        .and_then(|n| owners_for_crate_name(&n).ok())
-                    {
-                        Some(v) => format!("; cc {}", format_owners_to_cc(&v)),
-                        None => format!("no owner?"),
-                    }
-                } else {
-                    String::new()
-                }
-            )
-            .unwrap();
-            writeln!(table, "<details>\n").unwrap();
-            for row in affected {
-                let author = if cause == SuspectedCause::Unknown {
-                    row.5
-                } else {
-                    format!("`{}`", row.5)
-                };
-                writeln!(
-                    table,
-                    " * {}: [{}]({}) v. [{}]({}){}",
-                    row.0,
-                    row.1,
-                    row.2,
-                    row.3,
-                    row.4,
-                    if cc_ty.causes() {
-                        format!("; cc {}", author)
-                    } else {
-                        String::new()
-                    }
-                )
-                .unwrap();
-            }
-            writeln!(table, "\n</details>\n").unwrap();
+    std::fs::write(
+        format!("{}-crate-list.txt", category),
+        crate_list.trim_end_matches(","),
+    )
+    .unwrap();
+
+    Ok(Some(rows))
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().collect::<Vec<_>>();
+    let format = take_format_flag(&mut args);
+    let categories = take_categories_flag(&mut args);
+    let experiment = args.get(1).unwrap_or_else(|| {
+        eprintln!("Usage: {} <experiment name>", args[0]);
+        std::process::exit(1);
+    });
+    let cc_ty = args.get(2).unwrap_or_else(|| {
+        eprintln!(
+            "Usage: {} <experiment name> <all|roots|none|print-list>",
+            args[0]
+        );
+        std::process::exit(1);
+    });
+    let cc_ty = match cc_ty.as_str() {
+        "all" => CcWho::All,
+        "roots" => CcWho::Roots,
+        "none" => CcWho::None,
+        "print-list" => CcWho::None,
+        _ => {
+            eprintln!("Wrong second parameter: {:?}", cc_ty);
+            eprintln!("Usage: {} <experiment name> <all|roots|none>", args[0]);
+            std::process::exit(1);
+        }
+    };
+    let matchers_path = args.get(3).map(String::as_str).unwrap_or("matchers.json");
+    let matcher_engine = MatcherEngine::load(std::path::Path::new(matchers_path))
+        .unwrap_or_else(|e| {
+            eprintln!("failed to load matcher config {:?}: {}", matchers_path, e);
+            std::process::exit(1);
+        });
+
+    let url = format!(
+        "https://crater-reports.s3.amazonaws.com/{}/config.json",
+        experiment
+    );
+    let config: Config = reqwest::get(&url)
+        .unwrap_or_else(|e| {
+            eprintln!("failed to get {:?}: {:?}", url, e);
+            std::process::exit(1);
+        })
+        .json()
+        .unwrap_or_else(|e| {
+            eprintln!("failed to deserialize response from {:?}: {:?}", url, e);
+            std::process::exit(1);
+        });
+
+    let mut sections = BTreeMap::new();
+    for category in &categories {
+        if let Some(rows) = process_category(experiment, category, &config, &matcher_engine)? {
+            sections.insert(category.clone(), rows);
         }
     }
-    std::io::stdout().write_all(table.as_bytes()).unwrap();
+
+    let output = match format {
+        OutputFormat::Markdown => report::markdown(&sections, cc_ty),
+        OutputFormat::Json => report::json(&sections)?,
+    };
+    std::io::stdout().write_all(output.as_bytes()).unwrap();
 
     Ok(())
 }
@@ -406,40 +380,6 @@ enum ToolchainType {
     End,
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
-enum SuspectedCause {
-    CompileError { crate_name: String },
-    DocumentaionError { crate_name: String },
-    TestFailure { crate_name: String },
-    DocTestFailure { crate_name: String },
-    Unknown,
-}
-
-impl fmt::Display for SuspectedCause {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let name = match self {
-            SuspectedCause::CompileError { crate_name } => crate_name.as_str(),
-            SuspectedCause::DocumentaionError { crate_name } => crate_name.as_str(),
-            SuspectedCause::TestFailure { crate_name } => crate_name.as_str(),
-            SuspectedCause::DocTestFailure { crate_name } => crate_name.as_str(),
-            SuspectedCause::Unknown => return write!(f, "unknown causes"),
-        };
-        write!(f, "{}", name)
-    }
-}
-
-impl SuspectedCause {
-    fn crate_name(&self) -> Option<&str> {
-        match self {
-            SuspectedCause::CompileError { crate_name } => Some(crate_name.as_str()),
-            SuspectedCause::DocumentaionError { crate_name } => Some(crate_name.as_str()),
-            SuspectedCause::TestFailure { crate_name } => Some(crate_name.as_str()),
-            SuspectedCause::DocTestFailure { crate_name } => Some(crate_name.as_str()),
-            SuspectedCause::Unknown => None,
-        }
-    }
-}
-
 impl Regression {
     fn new(id: CrateId) -> Self {
         Regression {
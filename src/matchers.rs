@@ -0,0 +1,316 @@
+//! Data-driven classification of crater build/test logs.
+//!
+//! Instead of hardcoding a fixed set of regexes in `main`, the taxonomy of
+//! "why did this crate regress" is loaded from a config file whose shape is
+//! modeled on GitHub Actions' problem matchers
+//! (<https://github.com/actions/toolkit/blob/main/docs/problem-matchers.md>):
+//! each matcher has an `owner` name and an ordered list of `pattern`s. A
+//! matcher with more than one pattern is a multi-line matcher: the first
+//! pattern must match a line to start the match, and the remaining patterns
+//! are tried against the following lines in order. A pattern marked `loop`
+//! repeats against consecutive lines until it stops matching before control
+//! moves on to the next pattern.
+//!
+//! Each pattern's `message`, `file`, `line`, `column` and `severity` keys are
+//! 1-based capture group indices into that pattern's `regexp`, exactly as in
+//! the GitHub Actions format. Of these, this tool only consumes `message`
+//! (folded into `SuspectedCause::crate_name`), `file` and `line`; `column`
+//! and `severity` are accepted so matcher files can be shared/authored
+//! against the upstream schema, but aren't surfaced anywhere yet.
+
+use regex::Regex;
+use std::fmt;
+use std::path::Path;
+
+/// The classification produced by running the matcher engine over a log.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+pub struct SuspectedCause {
+    pub category: String,
+    pub crate_name: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+}
+
+impl SuspectedCause {
+    pub fn unknown() -> Self {
+        SuspectedCause {
+            category: "unknown".to_string(),
+            crate_name: None,
+            file: None,
+            line: None,
+        }
+    }
+
+    pub fn is_unknown(&self) -> bool {
+        self.category == "unknown"
+    }
+}
+
+impl fmt::Display for SuspectedCause {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.crate_name {
+            Some(crate_name) if !self.is_unknown() => write!(f, "{}", crate_name),
+            _ => write!(f, "unknown causes"),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct MatcherFile {
+    #[serde(rename = "problemMatcher")]
+    problem_matcher: Vec<RawMatcher>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct RawMatcher {
+    owner: String,
+    pattern: Vec<RawPattern>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct RawPattern {
+    regexp: String,
+    message: Option<usize>,
+    file: Option<usize>,
+    line: Option<usize>,
+    #[allow(dead_code)]
+    column: Option<usize>,
+    #[allow(dead_code)]
+    severity: Option<usize>,
+    #[serde(rename = "loop", default)]
+    loop_: bool,
+}
+
+#[derive(Debug)]
+struct CompiledPattern {
+    regex: Regex,
+    message: Option<usize>,
+    file: Option<usize>,
+    line: Option<usize>,
+    loop_: bool,
+}
+
+/// A compiled matcher: an ordered chain of regexes describing how to
+/// recognize a category of error and, for multi-line matchers, how to pull
+/// its location out of the lines that follow.
+#[derive(Debug)]
+pub struct Matcher {
+    owner: String,
+    patterns: Vec<CompiledPattern>,
+}
+
+/// An ordered collection of matchers, tried top to bottom against each line
+/// of a log.
+#[derive(Debug)]
+pub struct MatcherEngine {
+    matchers: Vec<Matcher>,
+}
+
+impl MatcherEngine {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read matcher config {}: {}", path.display(), e))?;
+        let file: MatcherFile = serde_json::from_str(&contents)
+            .map_err(|e| format!("failed to parse matcher config {}: {}", path.display(), e))?;
+        let matchers = file
+            .problem_matcher
+            .into_iter()
+            .map(|raw| {
+                let patterns = raw
+                    .pattern
+                    .into_iter()
+                    .map(|p| {
+                        Ok(CompiledPattern {
+                            regex: Regex::new(&p.regexp)?,
+                            message: p.message,
+                            file: p.file,
+                            line: p.line,
+                            loop_: p.loop_,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, regex::Error>>()?;
+                Ok(Matcher {
+                    owner: raw.owner,
+                    patterns,
+                })
+            })
+            .collect::<Result<Vec<_>, regex::Error>>()?;
+        Ok(MatcherEngine { matchers })
+    }
+
+    /// Classify a log, returning one `SuspectedCause` per place a matcher
+    /// fired. `default_crate_name` is used to fill in `crate_name` for
+    /// matchers whose patterns don't capture a `message`.
+    pub fn classify(&self, log: &str, default_crate_name: &str) -> Vec<SuspectedCause> {
+        let lines = log.lines().collect::<Vec<_>>();
+        let mut causes = Vec::new();
+        for matcher in &self.matchers {
+            causes.extend(matcher.run(&lines, default_crate_name));
+        }
+        causes
+    }
+}
+
+impl Matcher {
+    fn run(&self, lines: &[&str], default_crate_name: &str) -> Vec<SuspectedCause> {
+        let mut causes = Vec::new();
+        let first = &self.patterns[0];
+        let mut i = 0;
+        while i < lines.len() {
+            let Some(caps) = first.regex.captures(lines[i]) else {
+                i += 1;
+                continue;
+            };
+            let mut cause = SuspectedCause {
+                category: self.owner.clone(),
+                crate_name: None,
+                file: None,
+                line: None,
+            };
+            apply_captures(first, &caps, &mut cause);
+
+            let mut cursor = i + 1;
+            let mut pat_idx = 1;
+            while pat_idx < self.patterns.len() {
+                let pattern = &self.patterns[pat_idx];
+                match lines.get(cursor).and_then(|l| pattern.regex.captures(l)) {
+                    Some(caps) => {
+                        apply_captures(pattern, &caps, &mut cause);
+                        cursor += 1;
+                        if !pattern.loop_ {
+                            pat_idx += 1;
+                        }
+                    }
+                    None if pattern.loop_ => pat_idx += 1,
+                    None => break,
+                }
+            }
+
+            if cause.crate_name.is_none() {
+                cause.crate_name = Some(default_crate_name.to_string());
+            }
+            causes.push(cause);
+            i = cursor.max(i + 1);
+        }
+        causes
+    }
+}
+
+fn apply_captures(pattern: &CompiledPattern, caps: &regex::Captures, cause: &mut SuspectedCause) {
+    if let Some(m) = pattern.message.and_then(|idx| caps.get(idx)) {
+        cause.crate_name = Some(m.as_str().to_string());
+    }
+    if let Some(m) = pattern.file.and_then(|idx| caps.get(idx)) {
+        cause.file = Some(m.as_str().to_string());
+    }
+    if let Some(m) = pattern.line.and_then(|idx| caps.get(idx)) {
+        cause.line = m.as_str().parse().ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine_from_json(json: &str) -> MatcherEngine {
+        let path = std::env::temp_dir().join(format!(
+            "crater-generate-report-test-{}-{:?}.json",
+            json.len(),
+            std::thread::current().id()
+        ));
+        std::fs::write(&path, json).unwrap();
+        let engine = MatcherEngine::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        engine
+    }
+
+    #[test]
+    fn loads_shipped_matchers_config() {
+        MatcherEngine::load(Path::new("matchers.json")).expect("matchers.json should parse");
+    }
+
+    #[test]
+    fn single_line_matcher_classifies_compile_error() {
+        let engine = engine_from_json(
+            r#"{
+                "problemMatcher": [
+                    {
+                        "owner": "compile-error",
+                        "pattern": [
+                            { "regexp": "[Cc]ould not compile `([^)]+?)`", "message": 1 }
+                        ]
+                    }
+                ]
+            }"#,
+        );
+        let causes = engine.classify("error[E0308]\nCould not compile `foo`.\n", "foo");
+        assert_eq!(causes.len(), 1);
+        assert_eq!(causes[0].category, "compile-error");
+        assert_eq!(causes[0].crate_name.as_deref(), Some("foo"));
+    }
+
+    #[test]
+    fn multi_line_matcher_captures_location_from_following_line() {
+        let engine = engine_from_json(
+            r#"{
+                "problemMatcher": [
+                    {
+                        "owner": "unresolved-import",
+                        "pattern": [
+                            { "regexp": "error\\[E0432\\]: unresolved import `([^`]+)`", "message": 1 },
+                            { "regexp": "\\s*--> (.+):(\\d+):(\\d+)", "file": 1, "line": 2, "column": 3 }
+                        ]
+                    }
+                ]
+            }"#,
+        );
+        let log = "error[E0432]: unresolved import `foo::bar`\n --> src/lib.rs:3:5\n";
+        let causes = engine.classify(log, "foo");
+        assert_eq!(causes.len(), 1);
+        assert_eq!(causes[0].crate_name.as_deref(), Some("foo::bar"));
+        assert_eq!(causes[0].file.as_deref(), Some("src/lib.rs"));
+        assert_eq!(causes[0].line, Some(3));
+    }
+
+    #[test]
+    fn loop_pattern_consumes_consecutive_matching_lines() {
+        let engine = engine_from_json(
+            r#"{
+                "problemMatcher": [
+                    {
+                        "owner": "proc-macro-panic",
+                        "pattern": [
+                            { "regexp": "error: proc-macro derive panicked" },
+                            { "regexp": "\\s*= help: message: (.*)", "message": 1, "loop": true }
+                        ]
+                    }
+                ]
+            }"#,
+        );
+        let log = "error: proc-macro derive panicked\n  = help: message: first\n  = help: message: second\nnext line\n";
+        let causes = engine.classify(log, "foo");
+        assert_eq!(causes.len(), 1);
+        // the loop pattern keeps matching until "next line", so the last
+        // repeated capture wins.
+        assert_eq!(causes[0].crate_name.as_deref(), Some("second"));
+    }
+
+    #[test]
+    fn falls_back_to_default_crate_name_when_no_message_captured() {
+        let engine = engine_from_json(
+            r#"{
+                "problemMatcher": [
+                    {
+                        "owner": "test-failure",
+                        "pattern": [
+                            { "regexp": "error: test failed, to rerun pass '--lib'" }
+                        ]
+                    }
+                ]
+            }"#,
+        );
+        let causes = engine.classify("error: test failed, to rerun pass '--lib'\n", "some-crate");
+        assert_eq!(causes.len(), 1);
+        assert_eq!(causes[0].crate_name.as_deref(), Some("some-crate"));
+    }
+}
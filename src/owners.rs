@@ -0,0 +1,181 @@
+//! Looking up crates.io owners, with an on-disk cache and retry/backoff.
+//!
+//! A large experiment can re-mention the same few hundred crates across
+//! thousands of regressions, so `owners_for_crate_name` is backed by a
+//! TTL'd on-disk cache to avoid re-querying crates.io for crates we've
+//! already resolved, and retries transient failures (429s honoring
+//! `Retry-After`, 5xx, and transport-level errors like connection resets,
+//! all with exponential backoff) instead of aborting the whole run.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const CACHE_PATH: &str = "crates-io-owners-cache.json";
+const CACHE_TTL: Duration = Duration::from_secs(60 * 60 * 24);
+const MAX_ATTEMPTS: u32 = 5;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+lazy_static::lazy_static! {
+    static ref CLIENT: reqwest::Client = reqwest::Client::new();
+    static ref CACHE: Mutex<Cache> = Mutex::new(Cache::load());
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+struct CacheEntry {
+    owners: Vec<String>,
+    fetched_at: u64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
+struct Cache {
+    #[serde(flatten)]
+    entries: HashMap<String, CacheEntry>,
+}
+
+impl Cache {
+    fn load() -> Self {
+        std::fs::read_to_string(CACHE_PATH)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) {
+        if let Ok(serialized) = serde_json::to_string(self) {
+            let _ = std::fs::write(CACHE_PATH, serialized);
+        }
+    }
+
+    fn get(&self, package: &str) -> Option<Vec<String>> {
+        let entry = self.entries.get(package)?;
+        let age = now().saturating_sub(entry.fetched_at);
+        if age < CACHE_TTL.as_secs() {
+            Some(entry.owners.clone())
+        } else {
+            None
+        }
+    }
+
+    fn insert(&mut self, package: &str, owners: Vec<String>) {
+        self.entries.insert(
+            package.to_string(),
+            CacheEntry {
+                owners,
+                fetched_at: now(),
+            },
+        );
+        self.save();
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct CratesIoOwners {
+    users: Vec<CratesIoUser>,
+}
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum CratesIoUserKind {
+    User,
+    Team,
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct CratesIoUser {
+    kind: CratesIoUserKind,
+    login: String,
+    url: String,
+}
+
+impl CratesIoUser {
+    fn gh_username(&self) -> Option<&str> {
+        let prefix = "https://github.com/";
+        if self.url.starts_with(prefix) && self.kind == CratesIoUserKind::User {
+            Some(&self.login)
+        } else {
+            None
+        }
+    }
+}
+
+/// Resolve the GitHub usernames of a crates.io package's owners, consulting
+/// (and populating) the on-disk cache first.
+pub fn owners_for_crate_name(package: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    if let Some(owners) = CACHE.lock().unwrap().get(package) {
+        return Ok(owners);
+    }
+
+    let owners = fetch_owners(package)?;
+    CACHE.lock().unwrap().insert(package, owners.clone());
+    Ok(owners)
+}
+
+fn fetch_owners(package: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut backoff = INITIAL_BACKOFF;
+    for attempt in 1..=MAX_ATTEMPTS {
+        let mut response = match CLIENT
+            .get(&format!(
+                "https://crates.io/api/v1/crates/{}/owners",
+                package
+            ))
+            .header(reqwest::header::USER_AGENT, "crater-generate-report")
+            .send()
+        {
+            Ok(response) => response,
+            Err(e) => {
+                if attempt == MAX_ATTEMPTS {
+                    return Err(format!(
+                        "crates.io owners lookup for {:?} failed: {}",
+                        package, e
+                    )
+                    .into());
+                }
+                eprintln!(
+                    "crates.io owners lookup for {:?} failed, retrying: {}",
+                    package, e
+                );
+                std::thread::sleep(backoff);
+                backoff *= 2;
+                continue;
+            }
+        };
+
+        let status = response.status();
+        if status.is_success() {
+            let owners: CratesIoOwners = response.json()?;
+            return Ok(owners
+                .users
+                .into_iter()
+                .flat_map(|u| u.gh_username().map(String::from))
+                .collect());
+        }
+
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(Duration::from_secs);
+
+        let retryable = status.as_u16() == 429 || status.is_server_error();
+        if !retryable || attempt == MAX_ATTEMPTS {
+            return Err(format!(
+                "crates.io owners lookup for {:?} failed with {}",
+                package, status
+            )
+            .into());
+        }
+
+        std::thread::sleep(retry_after.unwrap_or(backoff));
+        backoff *= 2;
+    }
+    unreachable!()
+}
@@ -0,0 +1,132 @@
+//! Rendering a classified set of regressions as either the Markdown table
+//! crater triagers paste into an issue, or structured JSON for downstream
+//! automation to consume directly. Both formats are organized by result
+//! category (`regressed`, `fixed`, ...), then by [`SuspectedCause`] within
+//! that category.
+
+use crate::matchers::SuspectedCause;
+use crate::CcWho;
+use crate::CrateId;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+/// One crate attributed to a [`SuspectedCause`] within a result category.
+#[derive(Debug, serde::Serialize)]
+pub struct Row {
+    pub id: CrateId,
+    pub start_log_url: String,
+    pub end_log_url: String,
+    pub owners: Vec<String>,
+    /// Lines present in the end log but not the start log (see [`crate::logdiff`]),
+    /// i.e. what's actually new about this toolchain run.
+    pub new_errors: Vec<String>,
+}
+
+type Sections = BTreeMap<String, BTreeMap<SuspectedCause, Vec<Row>>>;
+
+#[derive(Debug, serde::Serialize)]
+struct JsonEntry<'a> {
+    cause: &'a SuspectedCause,
+    affected: &'a [Row],
+}
+
+pub fn json(sections: &Sections) -> serde_json::Result<String> {
+    let sections = sections
+        .iter()
+        .map(|(category, rows)| {
+            let entries = rows
+                .iter()
+                .map(|(cause, affected)| JsonEntry { cause, affected })
+                .collect::<Vec<_>>();
+            (category.clone(), entries)
+        })
+        .collect::<BTreeMap<_, _>>();
+    serde_json::to_string_pretty(&sections)
+}
+
+pub fn markdown(sections: &Sections, cc_ty: CcWho) -> String {
+    let mut table = String::new();
+    for (category, rows) in sections {
+        writeln!(table, "# {}\n", category).unwrap();
+        write_category(&mut table, rows, cc_ty);
+    }
+    table
+}
+
+fn write_category(table: &mut String, rows: &BTreeMap<SuspectedCause, Vec<Row>>, cc_ty: CcWho) {
+    for (cause, affected) in rows {
+        if affected.len() == 1 {
+            let row = &affected[0];
+            writeln!(
+                table,
+                " * root: {}: [start]({}) v. [end]({}){}",
+                row.id,
+                row.start_log_url,
+                row.end_log_url,
+                if cc_ty.roots() {
+                    format!("; cc {}", crate::format_owners_to_cc(&row.owners))
+                } else {
+                    String::new()
+                }
+            )
+            .unwrap();
+            write_new_errors(table, row);
+        } else {
+            writeln!(
+                table,
+                "\nroot: {} - {} detected crates which regressed due to this{}",
+                cause,
+                affected.len(),
+                if cc_ty.roots() {
+                    match cause
+                        .crate_name
+                        .as_deref()
+                        .and_then(|n| crate::owners::owners_for_crate_name(n).ok())
+                    {
+                        Some(v) => format!("; cc {}", crate::format_owners_to_cc(&v)),
+                        None => "no owner?".to_string(),
+                    }
+                } else {
+                    String::new()
+                }
+            )
+            .unwrap();
+            writeln!(table, "<details>\n").unwrap();
+            for row in affected {
+                let cc = crate::format_owners_to_cc(&row.owners);
+                let author = if cause.is_unknown() {
+                    cc
+                } else {
+                    format!("`{}`", cc)
+                };
+                writeln!(
+                    table,
+                    " * {}: [start]({}) v. [end]({}){}",
+                    row.id,
+                    row.start_log_url,
+                    row.end_log_url,
+                    if cc_ty.causes() {
+                        format!("; cc {}", author)
+                    } else {
+                        String::new()
+                    }
+                )
+                .unwrap();
+                write_new_errors(table, row);
+            }
+            writeln!(table, "\n</details>\n").unwrap();
+        }
+    }
+}
+
+fn write_new_errors(table: &mut String, row: &Row) {
+    if row.new_errors.is_empty() {
+        return;
+    }
+    writeln!(
+        table,
+        "   <details><summary>new errors</summary>\n\n   ```\n{}\n   ```\n   </details>",
+        row.new_errors.join("\n")
+    )
+    .unwrap();
+}